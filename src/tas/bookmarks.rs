@@ -0,0 +1,61 @@
+use crate::core;
+
+/// Number of numbered bookmark slots, mirroring TASEdit's Bookmarks panel (slots 0-9).
+pub const SLOT_COUNT: usize = 10;
+
+/// A user-pinned, named navigation point: a frame, the savestate needed to jump straight back
+/// to it, and a thumbnail of what the screen looked like there.
+pub struct Bookmark {
+    pub frame: u32,
+    pub state: Vec<u8>,
+    pub thumbnail: core::Frame,
+}
+
+/// Fixed-size set of numbered bookmark slots. Unlike greenzone entries these are user-pinned
+/// and survive edits elsewhere in the movie until explicitly overwritten or cleared.
+pub struct Bookmarks {
+    slots: [Option<Bookmark>; SLOT_COUNT],
+}
+
+impl Bookmarks {
+    pub fn new() -> Bookmarks {
+        Bookmarks {
+            slots: std::array::from_fn(|_| None),
+        }
+    }
+
+    /// Sets bookmark `slot`, returning `false` without effect if `slot` is out of range.
+    #[must_use]
+    pub fn set(&mut self, slot: usize, frame: u32, state: Vec<u8>, thumbnail: core::Frame) -> bool {
+        let Some(dest) = self.slots.get_mut(slot) else {
+            return false;
+        };
+        *dest = Some(Bookmark {
+            frame,
+            state,
+            thumbnail,
+        });
+        true
+    }
+
+    /// Clears bookmark `slot`, returning `false` without effect if `slot` is out of range.
+    #[must_use]
+    pub fn clear(&mut self, slot: usize) -> bool {
+        let Some(dest) = self.slots.get_mut(slot) else {
+            return false;
+        };
+        *dest = None;
+        true
+    }
+
+    pub fn get(&self, slot: usize) -> Option<&Bookmark> {
+        self.slots.get(slot)?.as_ref()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Bookmark)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(i, b)| b.as_ref().map(|b| (i, b)))
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+
+/// Number of most-recent frames (relative to the playback cursor) kept at full density, one
+/// savestate per frame, so scrubbing nearby frames is instant.
+const DENSE_WINDOW: u32 = 60;
+
+/// How many times the stride is allowed to double before the byte budget alone is left to
+/// thin things further.
+const MAX_TIER: u32 = 12;
+
+/// Bounded-memory, strided savestate cache.
+///
+/// Keeping a full savestate for every emulated frame would exhaust RAM on a long movie, so
+/// retained states get progressively sparser the further behind the playback cursor they are:
+/// every frame for the most recent [`DENSE_WINDOW`] frames, then every 2nd, every 4th, every
+/// 8th, and so on. A byte budget is enforced on top of that schedule by evicting the oldest
+/// surviving entries once it's exceeded.
+pub struct Greenzone {
+    entries: BTreeMap<u32, Vec<u8>>,
+    byte_budget: usize,
+    bytes_used: usize,
+}
+
+impl Greenzone {
+    /// Default byte budget: 512 MiB of retained savestates.
+    const DEFAULT_BYTE_BUDGET: usize = 512 * 1024 * 1024;
+
+    pub fn new(initial_state: Vec<u8>) -> Greenzone {
+        Greenzone::with_byte_budget(initial_state, Self::DEFAULT_BYTE_BUDGET)
+    }
+
+    pub fn with_byte_budget(initial_state: Vec<u8>, byte_budget: usize) -> Greenzone {
+        let mut greenzone = Greenzone {
+            entries: BTreeMap::new(),
+            byte_budget,
+            bytes_used: 0,
+        };
+        greenzone.insert(0, initial_state);
+        greenzone
+    }
+
+    pub fn set_byte_budget(&mut self, byte_budget: usize) {
+        self.byte_budget = byte_budget;
+        self.enforce_budget();
+    }
+
+    /// Offers a savestate for `frame`, keeping it only if the retention schedule (relative to
+    /// `cursor`) and byte budget allow it. Frame 0 is always retained as the restore fallback.
+    pub fn save(&mut self, frame: u32, cursor: u32, state: Vec<u8>) {
+        self.rethin(cursor);
+
+        let age = cursor.saturating_sub(frame);
+        if frame % Self::stride_for_age(age) == 0 {
+            self.insert(frame, state);
+            self.enforce_budget();
+        }
+    }
+
+    /// Returns the nearest retained state at or before `frame`; callers re-emulate forward from
+    /// there to reach the exact frame.
+    pub fn restore(&self, frame: u32) -> (u32, Vec<u8>) {
+        let (&f, state) = self
+            .entries
+            .range(..=frame)
+            .next_back()
+            .expect("frame 0 is always retained");
+        (f, state.clone())
+    }
+
+    /// Drops every retained state after `after` — its input may have changed.
+    pub fn invalidate(&mut self, after: u32) {
+        let stale: Vec<u32> = self.entries.range(after + 1..).map(|(&f, _)| f).collect();
+        for frame in stale {
+            self.remove(frame);
+        }
+    }
+
+    /// Stride, in frames, at which states this far behind the cursor are retained.
+    fn stride_for_age(age: u32) -> u32 {
+        if age <= DENSE_WINDOW {
+            1
+        } else {
+            let tier = (age - DENSE_WINDOW) / DENSE_WINDOW + 1;
+            1 << tier.min(MAX_TIER)
+        }
+    }
+
+    /// Re-applies the retention schedule to existing entries as `cursor` advances, dropping any
+    /// that have aged into a coarser tier than the frame they sit on satisfies.
+    fn rethin(&mut self, cursor: u32) {
+        let stale: Vec<u32> = self
+            .entries
+            .keys()
+            .copied()
+            .filter(|&frame| {
+                frame != 0 && frame % Self::stride_for_age(cursor.saturating_sub(frame)) != 0
+            })
+            .collect();
+        for frame in stale {
+            self.remove(frame);
+        }
+    }
+
+    fn enforce_budget(&mut self) {
+        while self.bytes_used > self.byte_budget && self.entries.len() > 1 {
+            // Frame 0 is the restore fallback and is never evicted, so skip past it when
+            // looking for the oldest entry to drop.
+            let Some(&oldest) = self.entries.keys().find(|&&f| f != 0) else {
+                break;
+            };
+            self.remove(oldest);
+        }
+    }
+
+    fn insert(&mut self, frame: u32, state: Vec<u8>) {
+        self.bytes_used += state.len();
+        if let Some(old) = self.entries.insert(frame, state) {
+            self.bytes_used -= old.len();
+        }
+    }
+
+    fn remove(&mut self, frame: u32) {
+        if let Some(state) = self.entries.remove(&frame) {
+            self.bytes_used -= state.len();
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::VecDeque;
+
+/// A single reversible change to the frame input buffer.
+///
+/// Records only the byte range touched and the bytes that occupied it immediately beforehand
+/// (a compact diff, not a full movie copy): `old` is what `data[start..start+old.len()]` held
+/// before the edit, and `len_after` is how long that range became afterward. In-place
+/// overwrites (`frame_mut`) keep `len_after == old.len()`; insertions and deletions change it.
+#[derive(Clone)]
+struct Edit {
+    start: usize,
+    old: Vec<u8>,
+    len_after: usize,
+}
+
+/// Bounded undo/redo log for edits to `Tas::data`.
+///
+/// Mirrors FCEUX's ring of undo records: once `capacity` entries are recorded, the oldest is
+/// dropped rather than letting the log grow without bound.
+pub struct History {
+    capacity: usize,
+    undo_stack: VecDeque<Edit>,
+    redo_stack: Vec<Edit>,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> History {
+        History {
+            capacity,
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.undo_stack.len() > self.capacity {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Records that the bytes previously at `data[start..start+old.len()]` now occupy
+    /// `data[start..start+len_after]`. Discards any pending redo history, matching normal
+    /// editor undo semantics: a fresh edit invalidates the redo branch.
+    pub fn record(&mut self, start: usize, old: Vec<u8>, len_after: usize) {
+        self.redo_stack.clear();
+        if self.undo_stack.len() == self.capacity {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(Edit {
+            start,
+            old,
+            len_after,
+        });
+    }
+
+    /// Reverts the most recent edit, returning the byte offset it touched so callers can
+    /// invalidate the greenzone from there.
+    pub fn undo(&mut self, data: &mut Vec<u8>) -> Option<usize> {
+        let edit = self.undo_stack.pop_back()?;
+        let start = edit.start;
+        self.redo_stack.push(Self::apply(edit, data));
+        Some(start)
+    }
+
+    /// Re-applies the most recently undone edit, returning the byte offset it touched.
+    pub fn redo(&mut self, data: &mut Vec<u8>) -> Option<usize> {
+        let edit = self.redo_stack.pop()?;
+        let start = edit.start;
+        self.undo_stack.push_back(Self::apply(edit, data));
+        Some(start)
+    }
+
+    /// Splices `edit.old` into `data` at `edit.start` and returns the inverse edit (capturing
+    /// what was just overwritten) to push onto the opposite stack.
+    fn apply(edit: Edit, data: &mut Vec<u8>) -> Edit {
+        let Edit {
+            start,
+            old,
+            len_after,
+        } = edit;
+        let replaced = data[start..start + len_after].to_vec();
+        data.splice(start..start + len_after, old.iter().cloned());
+        Edit {
+            start,
+            old: replaced,
+            len_after: old.len(),
+        }
+    }
+}
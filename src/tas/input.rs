@@ -0,0 +1,164 @@
+use std::ops::Range;
+
+/// A single controller's input model.
+#[derive(Clone, Debug)]
+pub enum InputPort {
+    Joypad(Joypad),
+}
+
+#[derive(Clone, Debug)]
+pub enum Joypad {
+    Snes,
+}
+
+impl InputPort {
+    pub fn frame_size(&self) -> usize {
+        match self {
+            InputPort::Joypad(j) => j.frame_size(),
+        }
+    }
+
+    pub fn default(&self, buf: &mut [u8]) {
+        match self {
+            InputPort::Joypad(j) => j.default(buf),
+        }
+    }
+
+    pub fn buttons(&self) -> &'static [&'static str] {
+        match self {
+            InputPort::Joypad(j) => j.buttons(),
+        }
+    }
+
+    /// Single-character, fm2/bk2-style code for each button, in the same order as `buttons()`.
+    pub fn log_chars(&self) -> &'static [char] {
+        match self {
+            InputPort::Joypad(j) => j.log_chars(),
+        }
+    }
+
+    pub fn is_pressed(&self, buf: &[u8], button: usize) -> bool {
+        match self {
+            InputPort::Joypad(j) => j.is_pressed(buf, button),
+        }
+    }
+
+    pub fn set_pressed(&self, buf: &mut [u8], button: usize, pressed: bool) {
+        match self {
+            InputPort::Joypad(j) => j.set_pressed(buf, button, pressed),
+        }
+    }
+}
+
+impl Joypad {
+    pub fn frame_size(&self) -> usize {
+        match self {
+            // One bit per button, bit-packed into 2 bytes.
+            Joypad::Snes => 2,
+        }
+    }
+
+    pub fn default(&self, buf: &mut [u8]) {
+        buf.fill(0);
+    }
+
+    pub fn buttons(&self) -> &'static [&'static str] {
+        match self {
+            Joypad::Snes => &[
+                "B", "Y", "Sel", "Str", "U", "D", "L", "R", "A", "X", "L", "R",
+            ],
+        }
+    }
+
+    /// Single-character, fm2/bk2-style code for each button, in the same order as `buttons()`.
+    pub fn log_chars(&self) -> &'static [char] {
+        match self {
+            Joypad::Snes => &['B', 'Y', 's', 'S', 'U', 'D', 'L', 'R', 'A', 'X', 'l', 'r'],
+        }
+    }
+
+    pub fn is_pressed(&self, buf: &[u8], button: usize) -> bool {
+        buf[button / 8] & (1 << (button % 8)) != 0
+    }
+
+    pub fn set_pressed(&self, buf: &mut [u8], button: usize, pressed: bool) {
+        let bit = 1 << (button % 8);
+        if pressed {
+            buf[button / 8] |= bit;
+        } else {
+            buf[button / 8] &= !bit;
+        }
+    }
+}
+
+/// Which players' input bytes a recording pass is allowed to touch. FCEUX calls this
+/// "Rec all / Rec 1P / Rec 2P...".
+#[derive(Clone, Copy, Debug)]
+pub struct PlayerMask(u32);
+
+impl PlayerMask {
+    pub fn all() -> PlayerMask {
+        PlayerMask(u32::MAX)
+    }
+
+    pub fn none() -> PlayerMask {
+        PlayerMask(0)
+    }
+
+    pub fn only(player: usize) -> PlayerMask {
+        PlayerMask(1 << player)
+    }
+
+    pub fn arm(&mut self, player: usize) {
+        self.0 |= 1 << player;
+    }
+
+    pub fn disarm(&mut self, player: usize) {
+        self.0 &= !(1 << player);
+    }
+
+    pub fn is_armed(&self, player: usize) -> bool {
+        self.0 & (1 << player) != 0
+    }
+}
+
+/// The ordered set of input ports for a movie (player 1..N), concatenated into a single
+/// per-frame byte layout: player 1's bytes, then player 2's, and so on.
+#[derive(Clone, Debug)]
+pub struct Ports(Vec<InputPort>);
+
+impl Ports {
+    pub fn new(ports: Vec<InputPort>) -> Ports {
+        assert!(!ports.is_empty(), "a movie needs at least one input port");
+        Ports(ports)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &InputPort> {
+        self.0.iter()
+    }
+
+    pub fn frame_size(&self) -> usize {
+        self.0.iter().map(InputPort::frame_size).sum()
+    }
+
+    pub fn default(&self, buf: &mut [u8]) {
+        for (player, port) in self.0.iter().enumerate() {
+            let range = self.player_range(player);
+            port.default(&mut buf[range]);
+        }
+    }
+
+    /// The byte range within a frame occupied by `player`'s port.
+    pub fn player_range(&self, player: usize) -> Range<usize> {
+        let start: usize = self.0[..player].iter().map(InputPort::frame_size).sum();
+        start..start + self.0[player].frame_size()
+    }
+}
@@ -0,0 +1,55 @@
+/// Per-frame lag tracking: whether the emulated core produced a new video frame that step.
+///
+/// Libretro cores signal a lag ("duplicate") frame by polling input without rendering, which
+/// `run_guest_frame` observes each step. Parallels `Tas::data` one bool per frame, shifted in
+/// lockstep by the same insert/delete operations so a recorded flag stays attached to the frame
+/// it was observed on.
+#[derive(Default)]
+pub struct LagLog {
+    flags: Vec<bool>,
+}
+
+impl LagLog {
+    pub fn new() -> LagLog {
+        LagLog::default()
+    }
+
+    pub fn is_lag(&self, frame: u32) -> bool {
+        self.flags.get(frame as usize).copied().unwrap_or(false)
+    }
+
+    /// Records whether `frame` was a lag frame, growing the log if needed.
+    pub fn set(&mut self, frame: u32, is_lag: bool) {
+        if frame as usize >= self.flags.len() {
+            self.flags.resize(frame as usize + 1, false);
+        }
+        self.flags[frame as usize] = is_lag;
+    }
+
+    /// Length of the run of consecutive lag frames ending immediately before `frame`
+    /// (exclusive). Used by `Tas::adjust_up` to know how much input to collapse.
+    pub fn lag_run_before(&self, frame: u32) -> u32 {
+        let mut n = 0;
+        while frame > n && self.is_lag(frame - n - 1) {
+            n += 1;
+        }
+        n
+    }
+
+    /// Shifts lag flags to match an `insert` of `count` (non-lag) frames at `idx`.
+    pub fn insert(&mut self, idx: u32, count: u32) {
+        if idx as usize > self.flags.len() {
+            self.flags.resize(idx as usize, false);
+        }
+        self.flags.splice(
+            idx as usize..idx as usize,
+            std::iter::repeat(false).take(count as usize),
+        );
+    }
+
+    /// Shifts lag flags to match a deletion of `count` frames starting at `idx`.
+    pub fn delete(&mut self, idx: u32, count: u32) {
+        let end = (idx + count).min(self.flags.len() as u32) as usize;
+        self.flags.drain(idx as usize..end);
+    }
+}
@@ -0,0 +1,100 @@
+/// A single user-placed marker: an FCEUX TASEdit-style named checkpoint.
+#[derive(Clone, Debug, Default)]
+pub struct Marker {
+    pub note: String,
+}
+
+/// Sparse, frame-indexed collection of [`Marker`]s that tracks movie length edits.
+///
+/// Mirrors the layout of `Tas::data`: one slot per frame, shifted in lockstep whenever frames
+/// are inserted or removed so a marker stays attached to the same in-game moment rather than
+/// the raw index it was placed at.
+#[derive(Default)]
+pub struct Markers {
+    slots: Vec<Option<Marker>>,
+}
+
+impl Markers {
+    pub fn new() -> Markers {
+        Markers::default()
+    }
+
+    /// Flips the marker at `frame` on or off, returning whether it is now set.
+    pub fn toggle(&mut self, frame: u32) -> bool {
+        let slot = self.slot_mut(frame);
+        if slot.is_some() {
+            *slot = None;
+            false
+        } else {
+            *slot = Some(Marker::default());
+            true
+        }
+    }
+
+    /// All marked frames in ascending order, for serialization.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &Marker)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(i, m)| m.as_ref().map(|m| (i as u32, m)))
+    }
+
+    pub fn note(&self, frame: u32) -> Option<&str> {
+        self.slots
+            .get(frame as usize)?
+            .as_ref()
+            .map(|m| m.note.as_str())
+    }
+
+    pub fn set_note(&mut self, frame: u32, note: String) {
+        self.slot_mut(frame)
+            .get_or_insert_with(Marker::default)
+            .note = note;
+    }
+
+    /// Returns the nearest marked frame strictly after `from`, if any.
+    pub fn next(&self, from: u32) -> Option<u32> {
+        self.slots
+            .iter()
+            .enumerate()
+            .skip(from as usize + 1)
+            .find(|(_, m)| m.is_some())
+            .map(|(i, _)| i as u32)
+    }
+
+    /// Returns the nearest marked frame strictly before `from`, if any.
+    pub fn prev(&self, from: u32) -> Option<u32> {
+        let before = &self.slots[..(from as usize).min(self.slots.len())];
+        before
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, m)| m.is_some())
+            .map(|(i, _)| i as u32)
+    }
+
+    /// Shifts marker slots to match an `insert` of `count` blank frames at `idx`.
+    pub fn insert(&mut self, idx: u32, count: u32) {
+        if idx as usize > self.slots.len() {
+            self.slots.resize(idx as usize, None);
+        }
+        self.slots.splice(
+            idx as usize..idx as usize,
+            std::iter::repeat(None).take(count as usize),
+        );
+    }
+
+    /// Shifts marker slots to match a deletion of `count` frames starting at `idx`.
+    pub fn delete(&mut self, idx: u32, count: u32) {
+        let start = (idx as usize).min(self.slots.len());
+        let end = (idx + count).min(self.slots.len() as u32) as usize;
+        self.slots.drain(start..end);
+    }
+
+    fn slot_mut(&mut self, frame: u32) -> &mut Option<Marker> {
+        if frame as usize >= self.slots.len() {
+            self.slots.resize(frame as usize + 1, None);
+        }
+        &mut self.slots[frame as usize]
+    }
+}
@@ -1,16 +1,31 @@
+use std::path::Path;
 use std::time::Instant;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 
 use crate::core::{self, Core};
 
+use self::bookmarks::Bookmarks;
 use self::greenzone::Greenzone;
+use self::history::History;
+use self::lag::LagLog;
+use self::markers::Markers;
 
+pub mod bookmarks;
 mod greenzone;
+mod history;
 pub mod input;
+mod lag;
+pub mod markers;
+mod project;
+mod text_log;
 
 pub struct Tas {
     core: Core,
+    /// Path to the libretro core used to load `core`, kept for [`Tas::save_project`].
+    core_path: String,
+    /// Path to the ROM passed to the core, kept for [`Tas::save_project`].
+    rom_path: String,
 
     // Playback state
     /// The frame the user expects to be visible on the screen.
@@ -23,14 +38,27 @@ pub struct Tas {
     run_mode: RunMode,
     last_host_frame: Instant,
     core_frame_fraction: f32,
+    /// TASEdit's "Follow cursor": while running, keep the piano roll scrolled to the playback
+    /// frame instead of leaving scroll position to the user.
+    follow_cursor: bool,
+    /// TASEdit's "Auto-restore last position": remember the playback cursor when a recording or
+    /// seek operation begins, and jump back to it once playback stops.
+    auto_restore_position: bool,
+    /// The frame to return to once the current run stops, captured by [`Tas::seek_to`] or
+    /// [`Tas::set_run_mode`] while `auto_restore_position` is on.
+    restore_point: Option<u32>,
 
     // Editor state
     greenzone: Greenzone,
     selected_frame: u32,
     selection_locked: bool,
 
-    input_port: input::InputPort,
+    input_ports: input::Ports,
     data: Vec<u8>,
+    markers: Markers,
+    history: History,
+    lag_log: LagLog,
+    bookmarks: Bookmarks,
 }
 
 pub struct Frame {}
@@ -47,46 +75,96 @@ pub enum RunMode {
 #[derive(Clone, Debug)]
 pub enum RecordMode {
     ReadOnly,
-    Insert(Vec<u8>),
-    Overwrite(Vec<u8>),
+    Insert {
+        data: Vec<u8>,
+        armed: input::PlayerMask,
+    },
+    Overwrite {
+        data: Vec<u8>,
+        armed: input::PlayerMask,
+    },
 }
 
 impl Tas {
+    /// Default number of undo entries retained before the oldest is dropped.
+    const DEFAULT_HISTORY_CAPACITY: usize = 200;
+
     pub fn new() -> Result<Tas> {
-        let mut core = unsafe {
-            Core::load(
-                "cores/bsnes2014_accuracy_libretro.dylib",
-                "/Users/jonathan/code/sm/ntsc.sfc",
-            )?
-        };
+        Tas::with_core(
+            "cores/bsnes2014_accuracy_libretro.dylib",
+            "/Users/jonathan/code/sm/ntsc.sfc",
+        )
+    }
+
+    /// Loads a specific core/ROM pair, remembering the paths so the session can later be
+    /// persisted with [`Tas::save_project`].
+    pub fn with_core(core_path: impl Into<String>, rom_path: impl Into<String>) -> Result<Tas> {
+        let core_path = core_path.into();
+        let rom_path = rom_path.into();
+        let mut core = unsafe { Core::load(&core_path, &rom_path)? };
 
-        let input_port = input::InputPort::Joypad(input::Joypad::Snes);
+        let input_ports = input::Ports::new(vec![input::InputPort::Joypad(input::Joypad::Snes)]);
         // Create an empty frame of input.
         let mut data = Vec::new();
-        data.resize(input_port.frame_size(), 0);
-        input_port.default(&mut data);
+        data.resize(input_ports.frame_size(), 0);
+        input_ports.default(&mut data);
 
         Ok(Tas {
             playback_cursor: 0,
             next_emulator_frame: 0,
             run_mode: RunMode::Running {
                 stop_at: None,
-                record_mode: RecordMode::Insert(data.clone()),
+                record_mode: RecordMode::Insert {
+                    data: data.clone(),
+                    armed: input::PlayerMask::all(),
+                },
             },
             last_host_frame: Instant::now(),
             core_frame_fraction: 0.,
+            follow_cursor: true,
+            auto_restore_position: false,
+            restore_point: None,
 
             greenzone: Greenzone::new(core.save_state()),
             selected_frame: 0,
             selection_locked: true,
 
             core,
+            core_path,
+            rom_path,
 
-            input_port,
+            input_ports,
             data,
+            markers: Markers::new(),
+            history: History::new(Self::DEFAULT_HISTORY_CAPACITY),
+            lag_log: LagLog::new(),
+            bookmarks: Bookmarks::new(),
         })
     }
 
+    /// Persists this session (core/ROM identity, port layout, the greenzone's frame-0
+    /// savestate, the input log, and markers) to `path` so it can be reopened with
+    /// [`Tas::load_project`].
+    pub fn save_project(&self, path: impl AsRef<Path>) -> Result<()> {
+        project::save(self, path)
+    }
+
+    /// Reconstructs a session previously written by [`Tas::save_project`].
+    pub fn load_project(path: impl AsRef<Path>) -> Result<Tas> {
+        project::load(path)
+    }
+
+    /// Exports the input log as a plain-text, one-line-per-frame dump (fm2/bk2-style) so
+    /// movies can be diffed and shared outside this tool.
+    pub fn export_input_log(&self, path: impl AsRef<Path>) -> Result<()> {
+        text_log::export(self, path)
+    }
+
+    /// Replaces the input log with one previously written by [`Tas::export_input_log`].
+    pub fn import_input_log(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        text_log::import(self, path)
+    }
+
     pub fn selected_frame(&self) -> u32 {
         self.selected_frame
     }
@@ -100,10 +178,15 @@ impl Tas {
     }
 
     pub fn run_guest_frame(&mut self) -> &core::Frame {
+        let frame = self.next_emulator_frame;
         self.core.run_frame();
+        self.lag_log.set(frame, self.core.lag);
         self.next_emulator_frame += 1;
-        self.greenzone
-            .save(self.next_emulator_frame, self.core.save_state());
+        self.greenzone.save(
+            self.next_emulator_frame,
+            self.playback_cursor,
+            self.core.save_state(),
+        );
         if self.playback_cursor < self.next_emulator_frame - 1 {
             let n = self.next_emulator_frame - self.playback_cursor - 1;
             self.playback_cursor += n;
@@ -137,6 +220,7 @@ impl Tas {
 
         let run_mode = std::mem::replace(&mut self.run_mode, RunMode::Paused);
 
+        let mut stopped_early = false;
         let result = match &run_mode {
             RunMode::Paused => &self.core.frame,
             RunMode::Running {
@@ -146,21 +230,20 @@ impl Tas {
                 while self.core_frame_fraction >= 1. {
                     if let Some(stop) = stop_at {
                         if self.playback_cursor >= *stop {
-                            self.run_mode = RunMode::Paused;
+                            stopped_early = true;
                             break;
                         }
                     }
 
                     match record_mode {
                         RecordMode::ReadOnly => {}
-                        RecordMode::Insert(data) => {
-                            assert!(data.len() == self.input_port.frame_size());
-                            self.insert(self.playback_cursor + 1, data);
+                        RecordMode::Insert { data, armed } => {
+                            assert!(data.len() == self.input_ports.frame_size());
+                            self.insert_armed(self.playback_cursor + 1, data, *armed);
                         }
-                        RecordMode::Overwrite(data) => {
-                            assert!(data.len() == self.input_port.frame_size());
-                            self.frame_mut(self.playback_cursor + 1)
-                                .copy_from_slice(data);
+                        RecordMode::Overwrite { data, armed } => {
+                            assert!(data.len() == self.input_ports.frame_size());
+                            self.overwrite_armed(self.playback_cursor + 1, data, *armed);
                         }
                     }
                     assert!(self.next_emulator_frame == self.playback_cursor + 1);
@@ -173,7 +256,11 @@ impl Tas {
             }
         };
 
-        self.run_mode = run_mode;
+        if stopped_early {
+            self.finish_running();
+        } else {
+            self.run_mode = run_mode;
+        }
 
         result
     }
@@ -183,19 +270,60 @@ impl Tas {
     }
 
     pub fn set_run_mode(&mut self, mode: RunMode) {
-        self.run_mode = mode;
+        match (&self.run_mode, &mode) {
+            (RunMode::Paused, RunMode::Running { .. }) => {
+                if self.auto_restore_position {
+                    self.restore_point.get_or_insert(self.playback_cursor);
+                }
+                self.run_mode = mode;
+            }
+            (RunMode::Running { .. }, RunMode::Paused) => self.finish_running(),
+            _ => self.run_mode = mode,
+        }
+    }
+
+    /// Transitions to [`RunMode::Paused`] and, if [`Tas::auto_restore_position`] armed a restore
+    /// point for this run, seeks back to it.
+    fn finish_running(&mut self) {
+        self.run_mode = RunMode::Paused;
+        if let Some(frame) = self.restore_point.take() {
+            self.seek_to_raw(frame);
+        }
+    }
+
+    /// Whether the piano roll should stay scrolled to the playback frame while running, rather
+    /// than leaving scroll position to the user.
+    pub fn follow_cursor(&self) -> bool {
+        self.follow_cursor
+    }
+
+    pub fn set_follow_cursor(&mut self, follow: bool) {
+        self.follow_cursor = follow;
+    }
+
+    /// Whether starting a recording or seek operation remembers the playback position so it can
+    /// be restored automatically once playback stops.
+    pub fn auto_restore_position(&self) -> bool {
+        self.auto_restore_position
+    }
+
+    pub fn set_auto_restore_position(&mut self, restore: bool) {
+        self.auto_restore_position = restore;
+        if !restore {
+            self.restore_point = None;
+        }
     }
 
     pub fn av_info(&self) -> libretro_ffi::retro_system_av_info {
         self.core.av_info
     }
 
-    pub fn input_port(&self) -> &input::InputPort {
-        &self.input_port
+    pub fn input_ports(&self) -> impl Iterator<Item = &input::InputPort> {
+        self.input_ports.iter()
     }
 
     pub fn movie_len(&self) -> u32 {
-        (self.data.len() / self.input_port.frame_size()) as u32
+        (self.data.len() / self.input_ports.frame_size()) as u32
     }
 
     /// Invalidates the greenzone after the specified index.
@@ -211,27 +339,194 @@ impl Tas {
     }
 
     pub fn frame(&self, idx: u32) -> &[u8] {
-        let size = self.input_port.frame_size();
+        let size = self.input_ports.frame_size();
         &self.data[idx as usize * size..][..size]
     }
 
     pub fn frame_mut(&mut self, idx: u32) -> &mut [u8] {
         self.invalidate(idx);
-        let size = self.input_port.frame_size();
-        &mut self.data[idx as usize * size..][..size]
+        let size = self.input_ports.frame_size();
+        let start = idx as usize * size;
+        let old = self.data[start..start + size].to_vec();
+        self.history.record(start, old, size);
+        &mut self.data[start..start + size]
     }
 
     pub fn insert(&mut self, idx: u32, buf: &[u8]) {
-        let size = self.input_port.frame_size();
-        assert_eq!(buf.len() % size, 0);
         self.invalidate(idx);
+        let insert_idx = self.splice_frames(idx, buf);
+        self.history.record(insert_idx, Vec::new(), buf.len());
+    }
+
+    /// Splices `buf` into `data` at `idx`, shifting markers and lag flags to match. Returns the
+    /// byte offset touched. Does not record undo history or invalidate the greenzone, so callers
+    /// that need to fold this into a larger atomic edit can manage those themselves.
+    fn splice_frames(&mut self, idx: u32, buf: &[u8]) -> usize {
+        let size = self.input_ports.frame_size();
+        assert_eq!(buf.len() % size, 0);
 
         let insert_idx = idx as usize * size;
         self.data
             .splice(insert_idx..insert_idx, buf.iter().cloned());
+        let frames = (buf.len() / size) as u32;
+        self.markers.insert(idx, frames);
+        self.lag_log.insert(idx, frames);
+        insert_idx
+    }
+
+    /// Inserts a new frame at `idx`, taking only the armed players' bytes from `data`; unarmed
+    /// players get their port's default (neutral) input instead.
+    fn insert_armed(&mut self, idx: u32, data: &[u8], armed: input::PlayerMask) {
+        let mut buf = vec![0; self.input_ports.frame_size()];
+        for (player, port) in self.input_ports.iter().enumerate() {
+            let range = self.input_ports.player_range(player);
+            if armed.is_armed(player) {
+                buf[range.clone()].copy_from_slice(&data[range]);
+            } else {
+                port.default(&mut buf[range]);
+            }
+        }
+        self.insert(idx, &buf);
+    }
+
+    /// Overwrites only the armed players' bytes at `idx` from `data`, leaving other players'
+    /// existing input at that frame untouched.
+    fn overwrite_armed(&mut self, idx: u32, data: &[u8], armed: input::PlayerMask) {
+        let armed_ranges: Vec<_> = (0..self.input_ports.len())
+            .filter(|&p| armed.is_armed(p))
+            .map(|p| self.input_ports.player_range(p))
+            .collect();
+        let frame = self.frame_mut(idx);
+        for range in armed_ranges {
+            frame[range.clone()].copy_from_slice(&data[range]);
+        }
+    }
+
+    /// Collapses the run of lag frames immediately before `frame` by erasing their input
+    /// records, shifting everything after into alignment with meaningful (non-lag) game
+    /// frames. Mirrors TASEdit's "adjust up". Stops short of any marked frame within the run
+    /// rather than silently discarding its note.
+    pub fn adjust_up(&mut self, frame: u32) {
+        let n = self.lag_log.lag_run_before(frame);
+        if n == 0 {
+            return;
+        }
+
+        let mut start_frame = frame - n;
+        if let Some(marked) = (start_frame..frame)
+            .rev()
+            .find(|&f| self.markers.note(f).is_some())
+        {
+            start_frame = marked + 1;
+        }
+        if start_frame >= frame {
+            return;
+        }
+        let n = frame - start_frame;
+
+        let size = self.input_ports.frame_size();
+        let start = start_frame as usize * size;
+        let end = frame as usize * size;
+
+        // Capture everything from `start` onward before mutating, so the erase below and any
+        // end-of-movie padding it triggers land in a single undo entry rather than two.
+        let old_tail = self.data[start..].to_vec();
+
+        self.data.drain(start..end);
+        self.markers.delete(start_frame, n);
+        self.lag_log.delete(start_frame, n);
+
+        if self.movie_len() <= self.playback_cursor {
+            let pad = self.playback_cursor - self.movie_len() + 1;
+            self.pad_end(pad);
+        }
+
+        let len_after = self.data.len() - start;
+        self.history.record(start, old_tail, len_after);
+
+        // The content that used to start at `frame` now starts at `start_frame`; rewind the
+        // greenzone from just before it so the shifted-in input is re-simulated if it differs.
+        self.invalidate(start_frame.saturating_sub(1));
+    }
+
+    /// Inserts a single blank lag frame before `frame`, the inverse of [`Tas::adjust_up`].
+    pub fn adjust_down(&mut self, frame: u32) {
+        let size = self.input_ports.frame_size();
+        let mut blank = vec![0; size];
+        self.input_ports.default(&mut blank);
+        self.insert(frame, &blank);
+        self.lag_log.set(frame, true);
+    }
+
+    /// Appends `frames` blank frames to the end of the movie. Does not record its own undo
+    /// entry; callers fold the padding into their own history record.
+    fn pad_end(&mut self, frames: u32) {
+        let size = self.input_ports.frame_size();
+        let mut one = vec![0; size];
+        self.input_ports.default(&mut one);
+        let filler: Vec<u8> = one
+            .iter()
+            .copied()
+            .cycle()
+            .take(size * frames as usize)
+            .collect();
+        self.splice_frames(self.movie_len(), &filler);
+    }
+
+    /// Reverts the most recent edit made through [`Tas::frame_mut`], [`Tas::insert`] or
+    /// [`Tas::adjust_up`].
+    pub fn undo(&mut self) {
+        let old_frames = self.movie_len();
+        if let Some(start) = self.history.undo(&mut self.data) {
+            let frame = (start / self.input_ports.frame_size()) as u32;
+            self.resync_frame_state(frame, old_frames);
+            self.invalidate(frame);
+        }
+    }
+
+    /// Re-applies the most recently undone edit.
+    pub fn redo(&mut self) {
+        let old_frames = self.movie_len();
+        if let Some(start) = self.history.redo(&mut self.data) {
+            let frame = (start / self.input_ports.frame_size()) as u32;
+            self.resync_frame_state(frame, old_frames);
+            self.invalidate(frame);
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    /// Reconciles marker and lag-log slot counts with a movie length that just changed out
+    /// from under them (an undo/redo of an [`Tas::insert`] or [`Tas::adjust_up`]) at `frame`.
+    fn resync_frame_state(&mut self, frame: u32, old_frames: u32) {
+        let new_frames = self.movie_len();
+        if new_frames < old_frames {
+            let removed = old_frames - new_frames;
+            self.markers.delete(frame, removed);
+            self.lag_log.delete(frame, removed);
+        } else if new_frames > old_frames {
+            let added = new_frames - old_frames;
+            self.markers.insert(frame, added);
+            self.lag_log.insert(frame, added);
+        }
     }
 
     pub fn seek_to(&mut self, frame: u32) {
+        if self.auto_restore_position {
+            self.restore_point.get_or_insert(self.playback_cursor);
+        }
+        self.seek_to_raw(frame);
+    }
+
+    /// Moves playback to `frame` without touching `restore_point`. Used both by [`Tas::seek_to`]
+    /// and to jump back to a remembered position once a run finishes.
+    fn seek_to_raw(&mut self, frame: u32) {
         self.playback_cursor = frame;
         let (f, state) = self.greenzone.restore(frame);
         self.next_emulator_frame = f;
@@ -239,32 +534,119 @@ impl Tas {
     }
 
     pub fn toggle_playback(&mut self) {
-        self.run_mode = match self.run_mode {
+        let mode = match self.run_mode {
             RunMode::Paused => RunMode::Running {
                 stop_at: None,
                 record_mode: RecordMode::ReadOnly,
             },
-            RunMode::Running {
-                stop_at: _,
-                record_mode: _,
-            } => RunMode::Paused,
-        }
+            RunMode::Running { .. } => RunMode::Paused,
+        };
+        self.set_run_mode(mode);
     }
 
     pub fn select_next(&mut self, n: u32) {
         let n = n.min(self.movie_len().saturating_sub(self.selected_frame() + 1) as u32);
         self.selected_frame += n;
         if self.selection_locked {
-            self.seek_to(self.playback_cursor + n);
+            // Raw seek: this cursor movement is the pause below, not an excursion to remember
+            // and restore from once playback later stops.
+            self.seek_to_raw(self.playback_cursor + n);
         }
-        self.run_mode = RunMode::Paused;
+        self.set_run_mode(RunMode::Paused);
     }
     pub fn select_prev(&mut self, n: u32) {
         let n = n.min(self.selected_frame);
         self.selected_frame -= n;
         if self.selection_locked {
-            self.seek_to(self.playback_cursor.saturating_sub(n));
+            self.seek_to_raw(self.playback_cursor.saturating_sub(n));
         }
-        self.run_mode = RunMode::Paused;
+        self.set_run_mode(RunMode::Paused);
+    }
+
+    /// Flips the marker at `frame` on or off, returning whether it is now set.
+    pub fn toggle_marker(&mut self, frame: u32) -> bool {
+        self.markers.toggle(frame)
+    }
+
+    pub fn marker_note(&self, frame: u32) -> Option<&str> {
+        self.markers.note(frame)
+    }
+
+    pub fn set_marker_note(&mut self, frame: u32, note: String) {
+        self.markers.set_note(frame, note);
+    }
+
+    /// Seeks playback to the nearest marked frame after the current selection, if any.
+    pub fn select_next_marker(&mut self) {
+        if let Some(frame) = self.markers.next(self.selected_frame) {
+            self.selected_frame = frame;
+            if self.selection_locked {
+                self.seek_to_raw(frame);
+            }
+            self.set_run_mode(RunMode::Paused);
+        }
+    }
+
+    /// Seeks playback to the nearest marked frame before the current selection, if any.
+    pub fn select_prev_marker(&mut self) {
+        if let Some(frame) = self.markers.prev(self.selected_frame) {
+            self.selected_frame = frame;
+            if self.selection_locked {
+                self.seek_to_raw(frame);
+            }
+            self.set_run_mode(RunMode::Paused);
+        }
+    }
+
+    /// Pins the current frame into bookmark `slot`, capturing the savestate and a framebuffer
+    /// thumbnail needed to jump straight back to it later. Errors if `slot` is out of range.
+    pub fn set_bookmark(&mut self, slot: usize) -> Result<()> {
+        let frame = self.playback_cursor;
+        let state = self.core.save_state();
+        let thumbnail = self.core.frame.clone();
+        if self.bookmarks.set(slot, frame, state, thumbnail) {
+            Ok(())
+        } else {
+            bail!(
+                "bookmark slot {slot} is out of range (0..{})",
+                bookmarks::SLOT_COUNT
+            );
+        }
+    }
+
+    /// Errors if `slot` is out of range.
+    pub fn clear_bookmark(&mut self, slot: usize) -> Result<()> {
+        if self.bookmarks.clear(slot) {
+            Ok(())
+        } else {
+            bail!(
+                "bookmark slot {slot} is out of range (0..{})",
+                bookmarks::SLOT_COUNT
+            );
+        }
+    }
+
+    pub fn bookmark(&self, slot: usize) -> Option<&bookmarks::Bookmark> {
+        self.bookmarks.get(slot)
+    }
+
+    pub fn bookmarks(&self) -> impl Iterator<Item = (usize, &bookmarks::Bookmark)> {
+        self.bookmarks.iter()
+    }
+
+    /// Restores bookmark `slot` directly from its pinned savestate, without re-emulating
+    /// forward through the greenzone. Returns `false` if the slot is empty or out of range.
+    pub fn jump_to_bookmark(&mut self, slot: usize) -> bool {
+        let Some(bookmark) = self.bookmarks.get(slot) else {
+            return false;
+        };
+        let frame = bookmark.frame;
+        let state = bookmark.state.clone();
+        self.set_run_mode(RunMode::Paused);
+        self.selected_frame = frame;
+        self.playback_cursor = frame;
+        self.next_emulator_frame = frame + 1;
+        self.core.restore_state(state);
+        true
     }
 }
@@ -0,0 +1,222 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+
+use super::bookmarks::{self, Bookmarks};
+use super::greenzone::Greenzone;
+use super::history::History;
+use super::input::{InputPort, Joypad, Ports};
+use super::lag::LagLog;
+use super::markers::Markers;
+use super::Tas;
+
+const MAGIC: &[u8; 8] = b"MVIPROJ1";
+
+/// Upper bound on any single length-prefixed blob this format reads, so a corrupted or
+/// maliciously crafted project file can't drive an unbounded allocation.
+const MAX_BLOB_LEN: u32 = 512 * 1024 * 1024;
+
+/// Upper bound on the number of input ports a project file can declare.
+const MAX_PORT_COUNT: u32 = 16;
+
+/// Writes `tas` to `path` in mvi's project format. See [`load`] for the reader.
+pub fn save(tas: &Tas, path: impl AsRef<Path>) -> Result<()> {
+    let mut w = File::create(path)?;
+    w.write_all(MAGIC)?;
+
+    write_string(&mut w, &tas.core_path)?;
+    write_string(&mut w, &tas.rom_path)?;
+
+    let ports: Vec<&InputPort> = tas.input_ports.iter().collect();
+    write_u32(&mut w, ports.len() as u32)?;
+    for port in &ports {
+        w.write_all(&[port_tag(port)])?;
+    }
+
+    // The greenzone always retains frame 0, so this is the initial savestate it was seeded with.
+    let (_, initial_state) = tas.greenzone.restore(0);
+    write_bytes(&mut w, &initial_state)?;
+    write_bytes(&mut w, &tas.data)?;
+
+    let markers: Vec<(u32, &str)> = tas
+        .markers
+        .iter()
+        .map(|(frame, marker)| (frame, marker.note.as_str()))
+        .collect();
+    write_u32(&mut w, markers.len() as u32)?;
+    for (frame, note) in markers {
+        write_u32(&mut w, frame)?;
+        write_string(&mut w, note)?;
+    }
+
+    let bookmarks: Vec<(usize, &bookmarks::Bookmark)> = tas.bookmarks.iter().collect();
+    write_u32(&mut w, bookmarks.len() as u32)?;
+    for (slot, bookmark) in bookmarks {
+        w.write_all(&[slot as u8])?;
+        write_u32(&mut w, bookmark.frame)?;
+        write_bytes(&mut w, &bookmark.state)?;
+    }
+
+    Ok(())
+}
+
+/// Reconstructs a [`Tas`] session previously written by [`save`]. The emulator is restored to
+/// the saved initial state; the greenzone otherwise starts empty and refills as the movie plays.
+pub fn load(path: impl AsRef<Path>) -> Result<Tas> {
+    let mut r = File::open(path)?;
+
+    let mut magic = [0; 8];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        bail!("not an mvi project file");
+    }
+
+    let core_path = read_string(&mut r)?;
+    let rom_path = read_string(&mut r)?;
+
+    let port_count = read_u32(&mut r)?;
+    if port_count == 0 || port_count > MAX_PORT_COUNT {
+        bail!("project file declares {port_count} input ports (must be 1..={MAX_PORT_COUNT})");
+    }
+    let mut ports = Vec::with_capacity(port_count as usize);
+    for _ in 0..port_count {
+        let mut tag = [0; 1];
+        r.read_exact(&mut tag)?;
+        ports.push(port_from_tag(tag[0])?);
+    }
+    let input_ports = Ports::new(ports);
+
+    let initial_state = read_bytes(&mut r)?;
+    let data = read_bytes(&mut r)?;
+
+    let frame_size = input_ports.frame_size();
+    if data.len() % frame_size != 0 {
+        bail!("project file input log length is not a multiple of the port frame size");
+    }
+    let movie_len = (data.len() / frame_size) as u32;
+
+    let marker_count = read_u32(&mut r)?;
+    let mut markers = Markers::new();
+    for _ in 0..marker_count {
+        let frame = read_u32(&mut r)?;
+        if frame >= movie_len {
+            bail!("project file marker at frame {frame} is beyond the movie's {movie_len} frames");
+        }
+        let note = read_string(&mut r)?;
+        markers.set_note(frame, note);
+    }
+
+    let bookmark_count = read_u32(&mut r)?;
+    if bookmark_count as usize > bookmarks::SLOT_COUNT {
+        bail!(
+            "project file declares {bookmark_count} bookmarks, more than the {} available slots",
+            bookmarks::SLOT_COUNT
+        );
+    }
+    let mut bookmark_records = Vec::with_capacity(bookmark_count as usize);
+    for _ in 0..bookmark_count {
+        let mut slot = [0; 1];
+        r.read_exact(&mut slot)?;
+        let frame = read_u32(&mut r)?;
+        if frame >= movie_len {
+            bail!(
+                "project file bookmark at frame {frame} is beyond the movie's {movie_len} frames"
+            );
+        }
+        let state = read_bytes(&mut r)?;
+        bookmark_records.push((slot[0] as usize, frame, state));
+    }
+
+    let mut core = unsafe { super::Core::load(&core_path, &rom_path)? };
+
+    // Regenerate each bookmark's thumbnail by transiently restoring its savestate, since the
+    // framebuffer itself isn't part of the serialized format.
+    let mut bookmarks = Bookmarks::new();
+    for (slot, frame, state) in bookmark_records {
+        core.restore_state(state.clone());
+        let thumbnail = core.frame.clone();
+        if !bookmarks.set(slot, frame, state, thumbnail) {
+            bail!(
+                "project file bookmark slot {slot} is out of range (0..{})",
+                bookmarks::SLOT_COUNT
+            );
+        }
+    }
+
+    core.restore_state(initial_state.clone());
+
+    Ok(Tas {
+        playback_cursor: 0,
+        next_emulator_frame: 0,
+        run_mode: super::RunMode::Paused,
+        last_host_frame: std::time::Instant::now(),
+        core_frame_fraction: 0.,
+        follow_cursor: true,
+        auto_restore_position: false,
+        restore_point: None,
+
+        greenzone: Greenzone::new(initial_state),
+        selected_frame: 0,
+        selection_locked: true,
+
+        core,
+        core_path,
+        rom_path,
+
+        input_ports,
+        data,
+        markers,
+        history: History::new(Tas::DEFAULT_HISTORY_CAPACITY),
+        lag_log: LagLog::new(),
+        bookmarks,
+    })
+}
+
+fn port_tag(port: &InputPort) -> u8 {
+    match port {
+        InputPort::Joypad(Joypad::Snes) => 0,
+    }
+}
+
+fn port_from_tag(tag: u8) -> Result<InputPort> {
+    match tag {
+        0 => Ok(InputPort::Joypad(Joypad::Snes)),
+        _ => bail!("unknown input port tag {tag}"),
+    }
+}
+
+fn write_u32(w: &mut impl Write, v: u32) -> io::Result<()> {
+    w.write_all(&v.to_le_bytes())
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn write_bytes(w: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    write_u32(w, bytes.len() as u32)?;
+    w.write_all(bytes)
+}
+
+fn read_bytes(r: &mut impl Read) -> Result<Vec<u8>> {
+    let len = read_u32(r)?;
+    if len > MAX_BLOB_LEN {
+        bail!("project file field of {len} bytes exceeds the {MAX_BLOB_LEN} byte limit");
+    }
+    let mut buf = vec![0; len as usize];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn write_string(w: &mut impl Write, s: &str) -> io::Result<()> {
+    write_bytes(w, s.as_bytes())
+}
+
+fn read_string(r: &mut impl Read) -> Result<String> {
+    let bytes = read_bytes(r)?;
+    String::from_utf8(bytes).map_err(|e| anyhow!("project file string is not valid utf8: {e}"))
+}
@@ -0,0 +1,95 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use super::history::History;
+use super::input::InputPort;
+use super::lag::LagLog;
+use super::markers::Markers;
+use super::Tas;
+
+/// Exports the movie's input log as a plain-text, one-line-per-frame dump (fm2/bk2-style): a
+/// block of characters per port (one per button, `.` for unpressed), ports separated by `|`, so
+/// movies can be diffed and reviewed outside this tool.
+pub fn export(tas: &Tas, path: impl AsRef<Path>) -> Result<()> {
+    let mut w = File::create(path)?;
+    let ports: Vec<&InputPort> = tas.input_ports.iter().collect();
+
+    for frame in 0..tas.movie_len() {
+        let data = tas.frame(frame);
+        let mut offset = 0;
+        for (i, port) in ports.iter().enumerate() {
+            if i > 0 {
+                write!(w, "|")?;
+            }
+            let size = port.frame_size();
+            let buf = &data[offset..offset + size];
+            for (button, &ch) in port.log_chars().iter().enumerate() {
+                write!(
+                    w,
+                    "{}",
+                    if port.is_pressed(buf, button) {
+                        ch
+                    } else {
+                        '.'
+                    }
+                )?;
+            }
+            offset += size;
+        }
+        writeln!(w)?;
+    }
+
+    Ok(())
+}
+
+/// Replaces `tas`'s input log with one previously written by [`export`]. Markers, lag tracking,
+/// and undo history are reset, since they no longer describe the replaced input.
+pub fn import(tas: &mut Tas, path: impl AsRef<Path>) -> Result<()> {
+    let ports: Vec<InputPort> = tas.input_ports.iter().cloned().collect();
+    let frame_size: usize = ports.iter().map(InputPort::frame_size).sum();
+
+    let mut data = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        let mut frame = vec![0u8; frame_size];
+        let mut offset = 0;
+        for (chunk, port) in line.split('|').zip(&ports) {
+            let size = port.frame_size();
+            let buf = &mut frame[offset..offset + size];
+            for (button, ch) in chunk.chars().enumerate() {
+                if ch == '.' {
+                    continue;
+                }
+                if button >= port.buttons().len() {
+                    bail!(
+                        "input log line has more characters than its port's {} buttons",
+                        port.buttons().len()
+                    );
+                }
+                port.set_pressed(buf, button, true);
+            }
+            offset += size;
+        }
+        if offset != frame_size {
+            bail!("input log line has fewer ports than the movie's {frame_size} bytes/frame");
+        }
+        data.extend_from_slice(&frame);
+    }
+
+    tas.data = data;
+    tas.markers = Markers::new();
+    tas.lag_log = LagLog::new();
+    tas.history = History::new(Tas::DEFAULT_HISTORY_CAPACITY);
+    tas.invalidate(0);
+
+    // The replaced log may be shorter than before; clamp rather than leave these pointing past
+    // its new end.
+    let last_frame = tas.movie_len().saturating_sub(1);
+    tas.playback_cursor = tas.playback_cursor.min(last_frame);
+    tas.selected_frame = tas.selected_frame.min(last_frame);
+
+    Ok(())
+}
@@ -2,10 +2,7 @@ use std::collections::VecDeque;
 
 use imgui::{ListClipper, Ui};
 
-use crate::tas::{
-    input::{InputPort, Joypad},
-    Tas,
-};
+use crate::tas::{RunMode, Tas};
 
 pub struct PianoRoll {
     last_selection: u32,
@@ -33,6 +30,7 @@ impl PianoRoll {
     const UNPRESSED_COLOR: [f32; 4] = color![45, 49, 55];
     const SELECT_HIGHLIGHT: [f32; 4] = color![128, 128, 128];
     const GREENZONE_HIGHLIGHT: [f32; 4] = color![16, 32, 16];
+    const MARKER_HIGHLIGHT: [f32; 4] = color![48, 40, 16];
 
     pub fn new() -> PianoRoll {
         PianoRoll {
@@ -57,18 +55,22 @@ impl PianoRoll {
                 let rows = tas.movie_len();
 
                 let _style = ui.push_style_var(imgui::StyleVar::ItemSpacing([0., 0.]));
-                if tas.selected_frame() != self.last_selection {
+                // While running, following the playback frame takes priority over the last
+                // selection change; otherwise scroll-on-select as usual.
+                let scroll_frame = if matches!(tas.run_mode(), RunMode::Running { .. }) {
+                    tas.follow_cursor().then(|| tas.playback_frame())
+                } else {
+                    (tas.selected_frame() != self.last_selection).then(|| tas.selected_frame())
+                };
+                if let Some(frame) = scroll_frame {
                     ui.set_scroll_y(
-                        (tas.selected_frame() as f64 * ui.text_line_height_with_spacing() as f64)
-                            as f32,
+                        (frame as f64 * ui.text_line_height_with_spacing() as f64) as f32,
                     );
                 }
 
                 let clipper = ListClipper::new(rows.try_into().unwrap()).begin(ui);
 
-                let buttons = match tas.input_port() {
-                    InputPort::Joypad(j) => j.buttons(),
-                };
+                let ports: Vec<_> = tas.input_ports().collect();
                 let number_column_width = rows.saturating_sub(1).ilog10() + 1;
                 let number_column_width = number_column_width.max(3) as usize;
 
@@ -76,8 +78,11 @@ impl PianoRoll {
                     (ui.window_size()[1] / ui.text_line_height_with_spacing()) as u32;
 
                 for row in clipper.iter() {
+                    let marker_note = tas.marker_note(row as u32);
                     let (highlight, frameno_color) = if row as u32 == tas.selected_frame() {
                         (Some(Self::SELECT_HIGHLIGHT), Self::SELECTED_FRAMENO_COLOR)
+                    } else if marker_note.is_some() {
+                        (Some(Self::MARKER_HIGHLIGHT), Self::FRAMENO_COLOR)
                     } else if tas.greenzone().restore(row as u32).0 == row as u32 {
                         (Some(Self::GREENZONE_HIGHLIGHT), Self::FRAMENO_COLOR)
                     } else {
@@ -103,17 +108,24 @@ impl PianoRoll {
                     } else {
                         ' '
                     };
+                    let note = marker_note.unwrap_or("");
                     ui.text_colored(
                         frameno_color,
-                        format!("{marker}{row:width$} ", width = number_column_width),
+                        format!("{marker}{row:width$} {note}", width = number_column_width),
                     );
 
-                    for text in buttons {
-                        ui.same_line();
-                        ui.text_colored(Self::UNPRESSED_COLOR, text);
+                    for (i, port) in ports.iter().enumerate() {
+                        if i > 0 {
+                            ui.same_line();
+                            ui.text_colored(Self::FRAMENO_COLOR, "|");
+                        }
+                        for text in port.buttons() {
+                            ui.same_line();
+                            ui.text_colored(Self::UNPRESSED_COLOR, text);
+                        }
                     }
                 }
             });
         self.last_selection = tas.selected_frame();
     }
-}
\ No newline at end of file
+}